@@ -0,0 +1,72 @@
+use std::path::Path;
+
+use r2d2::Pool;
+use r2d2_sqlite::SqliteConnectionManager;
+use rusqlite::Connection;
+
+/// Pooled handle type used by RPC readers. The ingest task does not go
+/// through this pool — it keeps its own dedicated writer connection so
+/// reads and writes never contend for the same handle.
+pub type SqlitePool = Pool<SqliteConnectionManager>;
+
+// Block-level metadata only: per-tx data lives in the normalized `tx`
+// table below so range scans and counts are index-friendly and an empty
+// block doesn't need special-casing (no more comma-joined `""` column).
+//
+// Keyed by `block_hash`, not `number`: a canonical reorg replaces the block
+// at a given height with a different hash at the *same* number, so an old
+// (now-orphaned) row and its replacement must be able to coexist — one
+// `canonical = 0`, the other `canonical = 1` — instead of colliding on a
+// shared primary key.
+const TX_PRIVY_SCHEMA: &str = "CREATE TABLE IF NOT EXISTS tx_privy (
+    block_hash TEXT PRIMARY KEY,
+    number INTEGER NOT NULL,
+    canonical BOOLEAN NOT NULL DEFAULT 1
+)";
+
+const TX_PRIVY_NUMBER_INDEX: &str =
+    "CREATE INDEX IF NOT EXISTS tx_privy_number_idx ON tx_privy (number)";
+
+// Keyed by `(block_hash, hash)`, not a bare `hash` primary key: a tx that
+// gets orphaned and then re-mined into a different block at the same or a
+// different height is a perfectly normal reorg outcome, and a global `hash`
+// PK would collide between its orphaned row (never deleted — orphaning only
+// flags `tx_privy`) and the row for its new block.
+const TX_SCHEMA: &str = "CREATE TABLE IF NOT EXISTS tx (
+    hash TEXT NOT NULL,
+    block_hash TEXT NOT NULL,
+    block_number INTEGER NOT NULL,
+    private BOOLEAN NOT NULL,
+    latency_ms INTEGER,
+    PRIMARY KEY (block_hash, hash)
+)";
+
+const TX_BLOCK_NUMBER_INDEX: &str =
+    "CREATE INDEX IF NOT EXISTS tx_block_number_idx ON tx (block_number)";
+
+/// Creates the `tx_privy`/`tx` tables if needed and switches the database
+/// to WAL mode so RPC reads never block the ingest task's writes (or vice
+/// versa).
+pub fn init(path: &Path) -> rusqlite::Result<()> {
+    let conn = Connection::open(path)?;
+    conn.pragma_update(None, "journal_mode", "WAL")?;
+    conn.execute(TX_PRIVY_SCHEMA, [])?;
+    conn.execute(TX_PRIVY_NUMBER_INDEX, [])?;
+    conn.execute(TX_SCHEMA, [])?;
+    conn.execute(TX_BLOCK_NUMBER_INDEX, [])?;
+    Ok(())
+}
+
+/// Builds the pool of read handles handed to `RethAnalExtApiServer`.
+pub fn read_pool(path: &Path) -> SqlitePool {
+    let manager = SqliteConnectionManager::file(path);
+    Pool::new(manager).expect("failed to build sqlite read pool")
+}
+
+/// Opens the single writer connection owned by the ingest task. Not
+/// pooled: there is exactly one writer, so there is nothing to pool.
+pub fn writer(path: &Path) -> rusqlite::Result<Connection> {
+    let conn = Connection::open(path)?;
+    conn.pragma_update(None, "journal_mode", "WAL")?;
+    Ok(conn)
+}
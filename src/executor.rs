@@ -0,0 +1,184 @@
+use std::time::Duration;
+
+use rusqlite::{params, Connection};
+use tokio::{
+    select,
+    sync::mpsc,
+    time::{interval, MissedTickBehavior},
+};
+
+use crate::BlockPrivy;
+
+/// A unit of work handed from the ingest task to the executor.
+#[derive(Debug, Clone)]
+pub enum PrivyOp {
+    /// Upserts a freshly classified, still-canonical block.
+    Upsert(BlockPrivy),
+    /// Flags a block displaced by a reorg as no longer canonical, without
+    /// touching its recorded classification. Identified by block hash, not
+    /// number: the replacement block lands at the same number, and a
+    /// number-keyed flag would get clobbered the moment that replacement is
+    /// upserted.
+    MarkOrphaned(String),
+}
+
+/// Rows are flushed once this many are queued, whichever comes first
+/// against `FLUSH_INTERVAL`.
+const BATCH_ROWS: usize = 32;
+
+/// Upper bound on how long a classified row can sit unflushed.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Bound on the channel between the ingest loop and the executor. Once
+/// full, `sender.send(..).await` on the ingest side applies backpressure
+/// instead of unbounded buffering.
+const CHANNEL_CAPACITY: usize = 1_024;
+
+/// Handle the ingest task uses to hand a unit of work off to the executor
+/// without waiting on the write to land on disk.
+pub type Sender = mpsc::Sender<PrivyOp>;
+
+/// Consumes classified blocks from the ingest loop and flushes them to
+/// sqlite in batches, either every `BATCH_ROWS` rows or `FLUSH_INTERVAL`,
+/// whichever comes first. Runs on its own task so a slow disk write never
+/// starves the `tx_listener`/`canon_state_listener` `select!` loop.
+pub struct PrivyExecutor {
+    conn: Connection,
+    receiver: mpsc::Receiver<PrivyOp>,
+}
+
+impl PrivyExecutor {
+    /// Builds the executor around `conn` and returns it paired with the
+    /// `Sender` the ingest task should hold onto.
+    pub fn new(conn: Connection) -> (Self, Sender) {
+        let (sender, receiver) = mpsc::channel(CHANNEL_CAPACITY);
+        (Self { conn, receiver }, sender)
+    }
+
+    pub async fn run(mut self) {
+        let mut ticker = interval(FLUSH_INTERVAL);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        let mut pending: Vec<PrivyOp> = Vec::with_capacity(BATCH_ROWS);
+
+        loop {
+            select! {
+                maybe_row = self.receiver.recv() => {
+                    match maybe_row {
+                        Some(row) => {
+                            pending.push(row);
+                            if pending.len() >= BATCH_ROWS {
+                                self.flush(&mut pending);
+                            }
+                        }
+                        // Sender dropped (node shutting down): flush what's
+                        // left and exit.
+                        None => {
+                            self.flush(&mut pending);
+                            return;
+                        }
+                    }
+                },
+                _ = ticker.tick() => {
+                    self.flush(&mut pending);
+                }
+            }
+        }
+    }
+
+    /// Flushes `pending` to sqlite, logging and giving up on just this
+    /// batch on a transaction/commit failure instead of panicking — a
+    /// disk-full or `SQLITE_BUSY` hiccup should not take down the detached
+    /// writer task and silently stop persistence for the rest of the run.
+    fn flush(&mut self, pending: &mut Vec<PrivyOp>) {
+        if pending.is_empty() {
+            return;
+        }
+
+        // Left undrained on failure, so these ops are retried on the next
+        // flush instead of being dropped.
+        let tx = match self.conn.transaction() {
+            Ok(tx) => tx,
+            Err(e) => {
+                tracing::error!("failed to open sqlite transaction, will retry next flush: {e}");
+                return;
+            }
+        };
+
+        for op in pending.drain(..) {
+            let savepoint = match tx.savepoint() {
+                Ok(savepoint) => savepoint,
+                Err(e) => {
+                    tracing::error!("failed to open sqlite savepoint, dropping this op: {e}");
+                    continue;
+                }
+            };
+
+            // `apply` stops at the first failed statement instead of
+            // plowing through the rest of the op, so a bad INSERT can't
+            // leave the block half-written underneath a savepoint that
+            // commits regardless.
+            match Self::apply(&savepoint, op) {
+                Ok(()) => {
+                    if let Err(e) = savepoint.commit() {
+                        tracing::error!("failed to commit sqlite savepoint, dropping this op: {e}");
+                    }
+                }
+                Err(e) => {
+                    tracing::error!("privy op failed, rolling back its savepoint: {e}");
+                    if let Err(e) = savepoint.rollback() {
+                        tracing::error!("failed to roll back sqlite savepoint: {e}");
+                    }
+                }
+            }
+        }
+
+        if let Err(e) = tx.commit() {
+            tracing::error!("failed to commit sqlite transaction, dropping this batch: {e}");
+        }
+    }
+
+    /// Applies a single op within `savepoint`, propagating the first
+    /// statement failure instead of swallowing it so the caller can roll
+    /// the savepoint back.
+    fn apply(savepoint: &rusqlite::Savepoint<'_>, op: PrivyOp) -> rusqlite::Result<()> {
+        match op {
+            PrivyOp::Upsert(row) => {
+                savepoint.execute(
+                    "DELETE FROM tx_privy WHERE block_hash = ?",
+                    params![row.block_hash],
+                )?;
+                savepoint.execute(
+                    "INSERT INTO tx_privy (block_hash, number, canonical) VALUES (?1, ?2, ?3)",
+                    params![row.block_hash, row.number, row.canonical],
+                )?;
+
+                savepoint.execute("DELETE FROM tx WHERE block_hash = ?", params![row.block_hash])?;
+                for timing in &row.tx_timings {
+                    // `OR REPLACE` as defense-in-depth: the `(block_hash, hash)`
+                    // primary key already makes a bare `INSERT` safe across
+                    // blocks, but this also keeps a re-sent `Upsert` for the
+                    // same block idempotent.
+                    savepoint.execute(
+                        "INSERT OR REPLACE INTO tx (hash, block_hash, block_number, private, latency_ms)
+                         VALUES (?1, ?2, ?3, ?4, ?5)",
+                        params![
+                            timing.hash,
+                            row.block_hash,
+                            row.number,
+                            timing.private,
+                            timing.latency_ms
+                        ],
+                    )?;
+                }
+            }
+            PrivyOp::MarkOrphaned(block_hash) => {
+                savepoint.execute(
+                    "UPDATE tx_privy SET canonical = 0 WHERE block_hash = ?",
+                    params![block_hash],
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -1,9 +1,10 @@
 use clap::Parser;
 use futures_util::StreamExt;
 use jsonrpsee::{
-    core::RpcResult,
+    core::{RpcResult, SubscriptionResult},
     proc_macros::rpc,
     types::{error::UNKNOWN_ERROR_CODE, ErrorObjectOwned},
+    PendingSubscriptionSink, SubscriptionMessage,
 };
 use reth::{
     builder::NodeHandle,
@@ -13,18 +14,86 @@ use reth::{
     transaction_pool::{FullTransactionEvent, TransactionPool},
 };
 use reth_node_ethereum::node::EthereumNode;
-use rusqlite::{params, Connection};
+use rusqlite::{params, Connection, OptionalExtension};
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, sync::Arc};
-use tokio::{select, sync::Mutex};
+use std::{
+    collections::{BTreeMap, HashMap},
+    time::Instant,
+};
+use tokio::{select, sync::broadcast};
 
-#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+mod db;
+mod executor;
+mod metrics;
+
+use executor::PrivyExecutor;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct BlockPrivy {
     pub number: BlockNumber,
+    /// Hex-encoded block hash. A canonical reorg re-occupies `number` with a
+    /// different hash, so `number` alone can't tell an orphaned block apart
+    /// from its replacement — `block_hash` is what actually identifies the
+    /// row.
+    pub block_hash: String,
     pub public_txs: Vec<String>,
     pub private_txs: Vec<String>,
+    /// `false` once this block has been displaced by a reorg. Kept in the
+    /// row instead of deleted so consumers can still see the orphaned
+    /// classification and detect reorg churn — surfaced via
+    /// `getBlockTxPrivyRange`'s `include_orphaned` flag and via a
+    /// `canonical: false` push on `subscribePrivy` when the reorg happens.
+    /// `getBlockTxPrivyByNumber` stays canonical-only by design: it answers
+    /// "what's at block N on the live chain right now," not "what ever was."
+    pub canonical: bool,
+    /// Mempool-to-inclusion latency for every tx in the block.
+    pub tx_timings: Vec<TxTiming>,
+}
+
+impl Default for BlockPrivy {
+    fn default() -> Self {
+        Self {
+            number: Default::default(),
+            block_hash: Default::default(),
+            public_txs: Default::default(),
+            private_txs: Default::default(),
+            canonical: true,
+            tx_timings: Default::default(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct TxTiming {
+    pub hash: String,
+    pub private: bool,
+    /// Milliseconds between first mempool sighting and block inclusion.
+    /// `None` if the tx was never observed in the mempool before its block
+    /// landed (always true for private txs).
+    pub latency_ms: Option<u64>,
 }
 
+/// Aggregate public/private tx counts over an inclusive block range. All
+/// counts are canonical-only; `orphaned_blocks` is reported alongside them
+/// purely as a churn signal, not folded into `private_ratio`.
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct PrivySummary {
+    pub from: BlockNumber,
+    pub to: BlockNumber,
+    pub total_blocks: u64,
+    pub total_public: u64,
+    pub total_private: u64,
+    pub private_ratio: f64,
+    /// Count of non-canonical (reorged-out) rows in `[from, to]`.
+    pub orphaned_blocks: u64,
+}
+
+/// Bound on `included_first_seen` so an unusually long run without a reorg
+/// doesn't grow it forever. Cleared outright rather than LRU-evicted: a
+/// reorg deep enough to need an entry older than this is not something
+/// this node is expected to handle gracefully anyway.
+const MAX_INCLUDED_FIRST_SEEN: usize = 10_000;
+
 fn main() {
     Cli::<RethAnalCliExt>::parse()
         .run(|builder, args| async move {
@@ -32,19 +101,21 @@ fn main() {
             let db_path = builder.data_dir().data_dir_path();
             let db_anal_sqlite3 = db_path.join(args.anal_db).clone();
             let db_anal_sqlite3 = db_anal_sqlite3.as_path();
-            let sqlite_conn = Connection::open(db_anal_sqlite3).unwrap();
-            sqlite_conn.execute(
-                "CREATE TABLE IF NOT EXISTS tx_privy (
-                    number INTEGER PRIMARY KEY,
-                    public_txs TEXT,
-                    private_txs TEXT
-                )",
-                [],
-            )?;
+            db::init(db_anal_sqlite3)?;
+
+            let metrics_addr = args
+                .anal_metrics_addr
+                .parse()
+                .expect("invalid --anal-metrics-addr");
+            metrics::install(metrics_addr);
+
+            // Fans out every freshly classified block to `anal_subscribePrivy`
+            // subscribers the moment the ingest task computes it.
+            let (privy_broadcast, _) = broadcast::channel(1_024);
+            let privy_broadcast_ingest = privy_broadcast.clone();
 
             // launch the node
-            let sqlite_conn_arc = Arc::new(Mutex::new(sqlite_conn));
-            let sqlite_conn_ext = sqlite_conn_arc.clone();
+            let sqlite_read_pool = db::read_pool(db_anal_sqlite3);
             let NodeHandle {
                 node,
                 node_exit_future,
@@ -53,7 +124,8 @@ fn main() {
                 .extend_rpc_modules(move |ctx| {
                     let ext = RethAnalExt {
                         provider: ctx.provider().clone(),
-                        sqlite_conn: sqlite_conn_ext.clone(),
+                        sqlite_pool: sqlite_read_pool.clone(),
+                        privy_broadcast: privy_broadcast.clone(),
                     };
                     ctx.modules.merge_configured(ext.into_rpc())?;
                     Ok(())
@@ -61,16 +133,37 @@ fn main() {
                 .launch()
                 .await?;
 
+            // Dedicated batching executor: the ingest loop only ever hands it
+            // classified rows over a channel, so a slow disk flush can never
+            // starve the `tx_listener`/`canon_state_listener` select loop below.
+            let sqlite_writer = db::writer(db_anal_sqlite3)?;
+            let (privy_executor, privy_sender) = PrivyExecutor::new(sqlite_writer);
+            node.task_executor
+                .spawn(Box::pin(privy_executor.run()));
+
             // create a new subscription to transactions and new canon state
             let mut tx_listener = node.pool.all_transactions_event_listener();
             let mut canon_state_listener = node.provider.subscribe_to_canonical_state();
-            let sqlite_conn_inserter = sqlite_conn_arc.clone();
             node.task_executor.spawn(Box::pin(async move {
                 // Simple KV store to denote if transactions are seen in the mempool
                 // Not querying from mempool as once the block is updated, it'll be removed
                 // from the pending mempool
                 let mut seen_txs = HashMap::new();
 
+                // Retains the true mempool first-seen time for a tx after
+                // it's included, in case its block is later orphaned and
+                // the tx re-mined — without this, the reorg re-seed below
+                // would stamp it with `Instant::now()` and its real
+                // propagation latency would read as ~0 on re-inclusion.
+                // Bounded rather than cleared on a cadence: a normal run
+                // never reorgs, so clearing on a timer would throw away
+                // live entries for blocks that turn out to stay canonical.
+                let mut included_first_seen = HashMap::new();
+
+                // Rolling private-tx ratio, reported via
+                // `reth_anal_private_ratio`.
+                let mut ratio_window = metrics::RollingRatio::new();
+
                 // Clone txpool
                 let txpool = node.pool.clone();
 
@@ -91,8 +184,9 @@ fn main() {
                                         }
                                     };
 
-                                    // Stores the transaction hash into the KV store
-                                    seen_txs.insert(tx_hash, true);
+                                    // Stores the first-seen time into the KV store; an entry
+                                    // already there means we saw it even earlier, so keep it.
+                                    seen_txs.entry(tx_hash).or_insert_with(Instant::now);
                                 }
                                 _ => {},
                             }
@@ -103,6 +197,7 @@ fn main() {
                             match result {
                                 Ok(e) => {
                                     let mut blocks: Vec<SealedBlock> = Vec::new();
+                                    let mut orphaned: Vec<(BlockNumber, String)> = Vec::new();
 
                                     match e {
                                         CanonStateNotification::Commit { new } =>{
@@ -110,48 +205,124 @@ fn main() {
                                                 blocks.push(v.block.clone());
                                             }
                                         },
-                                        CanonStateNotification::Reorg { old: _, new } => {
+                                        CanonStateNotification::Reorg { old, new } => {
+                                            // The old side of the reorg is no longer canonical:
+                                            // flag its rows instead of leaving them to look live,
+                                            // and re-seed `seen_txs` with its transactions so one
+                                            // re-mined on the new chain is still counted as
+                                            // previously-seen (public) rather than private.
+                                            //
+                                            // Flag by block hash, not number: the replacement
+                                            // block lands at this same height, and a number-keyed
+                                            // flag would get overwritten the moment that
+                                            // replacement is upserted below.
+                                            for (_, v) in old.blocks().into_iter() {
+                                                for tx in v.block.body.iter() {
+                                                    // Prefer the tx's real mempool first-seen
+                                                    // time (stashed when it was first included)
+                                                    // over `Instant::now()`, so a re-mined public
+                                                    // tx keeps its true propagation latency
+                                                    // instead of reading as ~0.
+                                                    let first_seen = included_first_seen
+                                                        .remove(&tx.hash())
+                                                        .unwrap_or_else(Instant::now);
+                                                    seen_txs.entry(tx.hash()).or_insert(first_seen);
+                                                }
+                                                orphaned.push((v.block.number, v.block.hash().encode_hex_with_prefix()));
+                                            }
+
                                             for (_, v) in new.blocks().into_iter() {
                                                 blocks.push(v.block.clone());
                                             }
                                         },
                                     };
 
-                                    // Sync
+                                    for (number, block_hash) in orphaned {
+                                        // Push a live orphan signal to `subscribePrivy` before
+                                        // persisting the flag — subscribers who only ever see
+                                        // `canonical: true` pushes have no way to learn a reorg
+                                        // happened until they separately poll. No tx data: the
+                                        // classification was already pushed once when this block
+                                        // was canonical, and re-deriving it here would just be
+                                        // the same bookkeeping `MarkOrphaned` already records.
+                                        let _ = privy_broadcast_ingest.send(BlockPrivy {
+                                            number,
+                                            block_hash: block_hash.clone(),
+                                            canonical: false,
+                                            ..Default::default()
+                                        });
+                                        let _ = privy_sender.send(executor::PrivyOp::MarkOrphaned(block_hash)).await;
+                                    }
+
                                     for block in blocks {
                                         let block_number = block.number;
+                                        let block_hash = block.hash().encode_hex_with_prefix();
                                         let body = block.body;
 
                                         let mut public_txs: Vec<String> = Vec::new();
                                         let mut private_txs: Vec<String> = Vec::new();
+                                        let mut tx_timings: Vec<TxTiming> = Vec::new();
 
                                         // Unblock guard and then saves the public txs
                                         for tx in body.iter() {
                                             let cur_hash = tx.hash();
-                                            if seen_txs.contains_key(&cur_hash) {
-                                                // Remove txhash (no memory leak)
-                                                seen_txs.remove(&cur_hash);
+                                            let hash_hex = cur_hash.encode_hex_with_prefix();
 
-                                                public_txs.push(cur_hash.encode_hex_with_prefix());
-                                            } else {
-                                                private_txs.push(cur_hash.encode_hex_with_prefix());
+                                            // Remove txhash (no memory leak), keeping its
+                                            // first-seen time to compute inclusion latency
+                                            let first_seen = seen_txs.remove(&cur_hash);
+
+                                            // Stash the real first-seen time in case this
+                                            // block is later orphaned and the tx re-mined —
+                                            // see `included_first_seen`'s declaration.
+                                            if let Some(t) = first_seen {
+                                                included_first_seen.insert(cur_hash, t);
                                             }
-                                        }
 
-                                        // Save to sqlite3
-                                        let guard = sqlite_conn_inserter.lock().await;
-                                        // Delete block if it already exists
-                                        let _ = guard.execute("DELETE FROM tx_privy WHERE number = ?", params![block_number]);
+                                            let private = first_seen.is_none();
+                                            let latency_ms = first_seen
+                                                .map(|t| t.elapsed().as_millis() as u64);
 
-                                        // Insert new block into sqlite3
-                                        let public_txs = public_txs.join(",");
-                                        let private_txs = private_txs.join(",");
+                                            if private {
+                                                private_txs.push(hash_hex.clone());
+                                            } else {
+                                                public_txs.push(hash_hex.clone());
+                                            }
 
-                                        let _ = guard.execute(
-                                        "INSERT INTO tx_privy (number, public_txs, private_txs) VALUES (?1, ?2, ?3)",
-                                            params![block_number, public_txs, private_txs],
+                                            tx_timings.push(TxTiming {
+                                                hash: hash_hex,
+                                                private,
+                                                latency_ms,
+                                            });
+                                        }
+
+                                        let rolling_ratio =
+                                            ratio_window.record(public_txs.len(), private_txs.len());
+                                        metrics::record_block(
+                                            public_txs.len(),
+                                            private_txs.len(),
+                                            seen_txs.len(),
+                                            rolling_ratio,
                                         );
 
+                                        let row = BlockPrivy {
+                                            number: block_number,
+                                            block_hash,
+                                            public_txs,
+                                            private_txs,
+                                            canonical: true,
+                                            tx_timings,
+                                        };
+
+                                        // Fan out to `anal_subscribePrivy` subscribers first (a
+                                        // lagging/absent receiver never blocks this), then hand
+                                        // the row to the executor task instead of writing it
+                                        // inline; this and the `MarkOrphaned` send above are the
+                                        // only points where the ingest loop can block, and only
+                                        // until the executor has room in its channel.
+                                        let _ = privy_broadcast_ingest.send(row.clone());
+                                        let _ = privy_sender.send(executor::PrivyOp::Upsert(row)).await;
+
                                         // Every 10 blocks, remove all queued txs to free up space
                                         if block_number % 10 == 0 {
                                             let queued_txs = txpool.queued_transactions();
@@ -159,8 +330,13 @@ fn main() {
                                             for tx in queued_txs.iter() {
                                                 queued_tx_hashes.push(*tx.hash())
                                             }
+                                            metrics::record_queued_eviction(queued_tx_hashes.len());
                                             txpool.remove_transactions(queued_tx_hashes);
                                         }
+
+                                        if included_first_seen.len() > MAX_INCLUDED_FIRST_SEEN {
+                                            included_first_seen.clear();
+                                        }
                                     }
                                 },
                                 _ => {},
@@ -180,6 +356,10 @@ struct RethAnalCliExt {
     /// Analytics database name
     #[arg(long, default_value = "reth-anal.sqlite3")]
     pub anal_db: String,
+
+    /// Address the Prometheus `/metrics` endpoint is served on
+    #[arg(long, default_value = "127.0.0.1:9100")]
+    pub anal_metrics_addr: String,
 }
 
 // Trait for the new namespace + method
@@ -188,11 +368,226 @@ struct RethAnalCliExt {
 pub trait RethAnalExtApi {
     #[method(name = "getBlockTxPrivyByNumber")]
     async fn get_block_tx_privy_by_number(&self, bn: BlockNumberOrTag) -> RpcResult<BlockPrivy>;
+
+    /// Returns every indexed block in the inclusive `[from, to]` range. A
+    /// number can appear more than once when `include_orphaned` is set and
+    /// a reorg has happened in range: one row per block hash that ever
+    /// occupied it, each with its own `canonical` flag — that's how a
+    /// caller detects reorg churn instead of only ever seeing `true`.
+    #[method(name = "getBlockTxPrivyRange")]
+    async fn get_block_tx_privy_range(
+        &self,
+        from: BlockNumber,
+        to: BlockNumber,
+        include_orphaned: bool,
+    ) -> RpcResult<Vec<BlockPrivy>>;
+
+    /// Returns aggregate public/private counts and ratio over the
+    /// inclusive `[from, to]` range, plus an `orphaned_blocks` churn count.
+    #[method(name = "getPrivySummary")]
+    async fn get_privy_summary(&self, from: BlockNumber, to: BlockNumber) -> RpcResult<PrivySummary>;
+
+    /// Streams a `BlockPrivy` the moment each new canonical block is
+    /// classified, and again — with `canonical: false` and no tx data —
+    /// the moment a previously-streamed block is displaced by a reorg, so
+    /// subscribers see churn live instead of only on the next poll. When
+    /// `private_only` is set, blocks with no private transactions are
+    /// skipped (orphan notifications are never filtered by this, since
+    /// they carry no tx data to judge).
+    #[subscription(name = "subscribePrivy" => "privy", item = BlockPrivy)]
+    async fn subscribe_privy(&self, private_only: bool) -> SubscriptionResult;
 }
 
 pub struct RethAnalExt<Provider> {
     pub provider: Provider,
-    pub sqlite_conn: Arc<Mutex<Connection>>,
+    pub sqlite_pool: db::SqlitePool,
+    pub privy_broadcast: broadcast::Sender<BlockPrivy>,
+}
+
+/// Loads a single block's classification from the normalized `tx_privy`/`tx`
+/// tables, or `None` if it hasn't been indexed. Only the canonical row for
+/// `number` is considered: a reorg can leave an orphaned row behind at the
+/// same number, and callers asking "what's at block N" mean the live chain.
+fn load_block_privy(conn: &Connection, number: BlockNumber) -> rusqlite::Result<Option<BlockPrivy>> {
+    let row: Option<(String, bool)> = conn
+        .query_row(
+            "SELECT block_hash, canonical FROM tx_privy WHERE number = ?1 AND canonical = 1",
+            [number],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?;
+
+    let Some((block_hash, canonical)) = row else {
+        return Ok(None);
+    };
+
+    let mut stmt = conn.prepare("SELECT hash, private, latency_ms FROM tx WHERE block_hash = ?")?;
+    let mut result = BlockPrivy {
+        number,
+        block_hash: block_hash.clone(),
+        canonical,
+        ..Default::default()
+    };
+
+    let rows = stmt.query_map([block_hash], |row| {
+        Ok(TxTiming {
+            hash: row.get(0)?,
+            private: row.get(1)?,
+            latency_ms: row.get(2)?,
+        })
+    })?;
+
+    for timing in rows {
+        let timing = timing?;
+        if timing.private {
+            result.private_txs.push(timing.hash.clone());
+        } else {
+            result.public_txs.push(timing.hash.clone());
+        }
+        result.tx_timings.push(timing);
+    }
+
+    Ok(Some(result))
+}
+
+/// Loads every indexed block in the inclusive `[from, to]` range with two
+/// range `SELECT`s instead of one query per block.
+///
+/// Canonical-only unless `include_orphaned` is set, in which case a number
+/// displaced by a reorg comes back as more than one row — one per block
+/// hash that has ever occupied it — so the caller can tell a reorg
+/// happened by seeing a `canonical: false` row share a number with a
+/// `canonical: true` one.
+fn load_block_privy_range(
+    conn: &Connection,
+    from: BlockNumber,
+    to: BlockNumber,
+    include_orphaned: bool,
+) -> rusqlite::Result<Vec<BlockPrivy>> {
+    // Keyed by `(number, block_hash)`, not bare `number`: with
+    // `include_orphaned` set, a reorged number maps to more than one row.
+    let mut blocks: BTreeMap<(BlockNumber, String), BlockPrivy> = BTreeMap::new();
+
+    let block_sql = if include_orphaned {
+        "SELECT number, block_hash, canonical FROM tx_privy WHERE number BETWEEN ?1 AND ?2"
+    } else {
+        "SELECT number, block_hash, canonical FROM tx_privy WHERE number BETWEEN ?1 AND ?2 AND canonical = 1"
+    };
+    let mut block_stmt = conn.prepare(block_sql)?;
+    let block_rows = block_stmt.query_map(params![from, to], |row| {
+        Ok((
+            row.get::<_, u64>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, bool>(2)?,
+        ))
+    })?;
+    for row in block_rows {
+        let (number, block_hash, canonical) = row?;
+        blocks.insert(
+            (number, block_hash.clone()),
+            BlockPrivy {
+                number,
+                block_hash,
+                canonical,
+                ..Default::default()
+            },
+        );
+    }
+
+    // Joined against `tx_privy` (instead of a bare `block_number BETWEEN`) so
+    // each tx lands on the row for the block hash it actually belongs to,
+    // not every row sharing its `block_number`.
+    let tx_sql = if include_orphaned {
+        "SELECT tx.hash, tx.block_hash, tx_privy.number, tx.private, tx.latency_ms
+         FROM tx
+         JOIN tx_privy ON tx_privy.block_hash = tx.block_hash
+         WHERE tx_privy.number BETWEEN ?1 AND ?2"
+    } else {
+        "SELECT tx.hash, tx.block_hash, tx_privy.number, tx.private, tx.latency_ms
+         FROM tx
+         JOIN tx_privy ON tx_privy.block_hash = tx.block_hash
+         WHERE tx_privy.canonical = 1 AND tx_privy.number BETWEEN ?1 AND ?2"
+    };
+    let mut tx_stmt = conn.prepare(tx_sql)?;
+    let tx_rows = tx_stmt.query_map(params![from, to], |row| {
+        Ok((
+            row.get::<_, u64>(2)?,
+            row.get::<_, String>(1)?,
+            TxTiming {
+                hash: row.get(0)?,
+                private: row.get(3)?,
+                latency_ms: row.get(4)?,
+            },
+        ))
+    })?;
+    for row in tx_rows {
+        let (block_number, block_hash, timing) = row?;
+        if let Some(block) = blocks.get_mut(&(block_number, block_hash)) {
+            if timing.private {
+                block.private_txs.push(timing.hash.clone());
+            } else {
+                block.public_txs.push(timing.hash.clone());
+            }
+            block.tx_timings.push(timing);
+        }
+    }
+
+    Ok(blocks.into_values().collect())
+}
+
+/// Computes aggregate public/private counts over the inclusive `[from, to]`
+/// range directly with `SELECT COUNT`/`SUM`, so the summary never has to
+/// materialize individual blocks.
+fn load_privy_summary(
+    conn: &Connection,
+    from: BlockNumber,
+    to: BlockNumber,
+) -> rusqlite::Result<PrivySummary> {
+    let total_blocks: u64 = conn.query_row(
+        "SELECT COUNT(*) FROM tx_privy WHERE number BETWEEN ?1 AND ?2 AND canonical = 1",
+        params![from, to],
+        |row| row.get(0),
+    )?;
+
+    // Reported alongside the canonical counts as a churn signal, not folded
+    // into them — a reorg within range should be visible, not change the
+    // ratio operators are already reading as a canonical-only number.
+    let orphaned_blocks: u64 = conn.query_row(
+        "SELECT COUNT(*) FROM tx_privy WHERE number BETWEEN ?1 AND ?2 AND canonical = 0",
+        params![from, to],
+        |row| row.get(0),
+    )?;
+
+    // Joined against `tx_privy.canonical` so an orphaned block's rows don't
+    // get counted alongside its replacement's — both can share a
+    // `block_number` post-reorg, but only the canonical one should count.
+    let (total_public, total_private): (u64, u64) = conn.query_row(
+        "SELECT
+            COALESCE(SUM(CASE WHEN NOT tx.private THEN 1 ELSE 0 END), 0),
+            COALESCE(SUM(CASE WHEN tx.private THEN 1 ELSE 0 END), 0)
+         FROM tx
+         JOIN tx_privy ON tx_privy.block_hash = tx.block_hash
+         WHERE tx_privy.canonical = 1 AND tx_privy.number BETWEEN ?1 AND ?2",
+        params![from, to],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+
+    let total = total_public + total_private;
+    let private_ratio = if total == 0 {
+        0.0
+    } else {
+        total_private as f64 / total as f64
+    };
+
+    Ok(PrivySummary {
+        from,
+        to,
+        total_blocks,
+        total_public,
+        total_private,
+        private_ratio,
+        orphaned_blocks,
+    })
 }
 
 #[async_trait::async_trait]
@@ -201,11 +596,10 @@ where
     Provider: BlockReaderIdExt + Clone + Unpin + 'static,
 {
     async fn get_block_tx_privy_by_number(&self, bn: BlockNumberOrTag) -> RpcResult<BlockPrivy> {
-        let conn = self.sqlite_conn.lock().await;
-
-        let mut stmt = conn
-            .prepare("SELECT number, public_txs, private_txs FROM tx_privy WHERE number = ?")
-            .unwrap();
+        let conn = self
+            .sqlite_pool
+            .get()
+            .map_err(|e| ErrorObjectOwned::owned(UNKNOWN_ERROR_CODE, e.to_string(), None::<()>))?;
 
         let bn = match bn.is_number() {
             true => bn.as_number().unwrap(),
@@ -221,35 +615,78 @@ where
             },
         };
 
-        let mut privy_iter = stmt
-            .query_map([bn], |row| {
-                let number: u64 = row.get(0)?;
+        match load_block_privy(&conn, bn) {
+            Ok(Some(result)) => Ok(result),
+            Ok(None) => Err(ErrorObjectOwned::owned(
+                UNKNOWN_ERROR_CODE,
+                "block not indexed",
+                None::<()>,
+            )),
+            Err(e) => Err(ErrorObjectOwned::owned(UNKNOWN_ERROR_CODE, e.to_string(), None::<()>)),
+        }
+    }
 
-                let public_txs: String = row.get(1)?;
-                let public_txs: Vec<&str> = public_txs.split(",").collect();
-                let public_txs = public_txs.into_iter().map(|x| x.to_string()).collect();
+    async fn get_block_tx_privy_range(
+        &self,
+        from: BlockNumber,
+        to: BlockNumber,
+        include_orphaned: bool,
+    ) -> RpcResult<Vec<BlockPrivy>> {
+        let conn = self
+            .sqlite_pool
+            .get()
+            .map_err(|e| ErrorObjectOwned::owned(UNKNOWN_ERROR_CODE, e.to_string(), None::<()>))?;
+
+        load_block_privy_range(&conn, from, to, include_orphaned)
+            .map_err(|e| ErrorObjectOwned::owned(UNKNOWN_ERROR_CODE, e.to_string(), None::<()>))
+    }
 
-                let private_txs: String = row.get(2)?;
-                let private_txs: Vec<&str> = private_txs.split(",").collect();
-                let private_txs = private_txs.into_iter().map(|x| x.to_string()).collect();
+    async fn get_privy_summary(&self, from: BlockNumber, to: BlockNumber) -> RpcResult<PrivySummary> {
+        let conn = self
+            .sqlite_pool
+            .get()
+            .map_err(|e| ErrorObjectOwned::owned(UNKNOWN_ERROR_CODE, e.to_string(), None::<()>))?;
 
-                Ok(BlockPrivy {
-                    number,
-                    public_txs,
-                    private_txs,
-                })
-            })
-            .map_err(|x| ErrorObjectOwned::owned(UNKNOWN_ERROR_CODE, x.to_string(), None::<()>))?;
+        load_privy_summary(&conn, from, to)
+            .map_err(|e| ErrorObjectOwned::owned(UNKNOWN_ERROR_CODE, e.to_string(), None::<()>))
+    }
 
-        if let Some(Ok(result)) = privy_iter.next() {
-            return Ok(result);
-        }
+    async fn subscribe_privy(
+        &self,
+        pending: PendingSubscriptionSink,
+        private_only: bool,
+    ) -> SubscriptionResult {
+        let sink = pending.accept().await?;
+        let mut rx = self.privy_broadcast.subscribe();
+
+        tokio::spawn(async move {
+            loop {
+                let block = match rx.recv().await {
+                    Ok(block) => block,
+                    // Fell behind the broadcast buffer: skip ahead rather
+                    // than closing the subscription.
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+
+                // Orphan notifications carry no tx data to judge, so
+                // `private_only` only ever filters freshly-classified
+                // (`canonical: true`) pushes.
+                if block.canonical && private_only && block.private_txs.is_empty() {
+                    continue;
+                }
+
+                let Ok(message) = SubscriptionMessage::from_json(&block) else {
+                    continue;
+                };
 
-        return Err(ErrorObjectOwned::owned(
-            UNKNOWN_ERROR_CODE,
-            "block not indexed",
-            None::<()>,
-        ));
+                if sink.send(message).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(())
     }
 }
 
@@ -264,9 +701,9 @@ mod tests {
         let _ = db
             .execute(
                 "CREATE TABLE IF NOT EXISTS tx_privy (
-                number INTEGER PRIMARY KEY,
-                public_txs TEXT,
-                private_txs TEXT
+                block_hash TEXT PRIMARY KEY,
+                number INTEGER NOT NULL,
+                canonical BOOLEAN NOT NULL DEFAULT 1
             )",
                 [],
             )
@@ -274,48 +711,102 @@ mod tests {
 
         let _ = db
             .execute(
-                "INSERT INTO tx_privy (number, public_txs, private_txs) VALUES (?1, ?2, ?3)",
-                params![1, "0x1,0x2,0x3", "0x5,0x6,0x7"],
+                "CREATE TABLE IF NOT EXISTS tx (
+                hash TEXT NOT NULL,
+                block_hash TEXT NOT NULL,
+                block_number INTEGER NOT NULL,
+                private BOOLEAN NOT NULL,
+                latency_ms INTEGER,
+                PRIMARY KEY (block_hash, hash)
+            )",
+                [],
             )
             .unwrap();
 
         let _ = db
-            .execute("DELETE FROM tx_privy WHERE number = ?", params![1])
+            .execute(
+                "INSERT INTO tx_privy (block_hash, number, canonical) VALUES (?1, ?2, ?3)",
+                params!["0xb1", 1, true],
+            )
             .unwrap();
-
         let _ = db
             .execute(
-                "INSERT INTO tx_privy (number, public_txs, private_txs) VALUES (?1, ?2, ?3)",
-                params![1, "0x1,0x2,0x3", "0x5,0x6,0x7"],
+                "INSERT INTO tx (hash, block_hash, block_number, private, latency_ms) VALUES (?1, ?2, ?3, ?4, ?5)",
+                params!["0x1", "0xb1", 1, false, Some(120_u64)],
             )
             .unwrap();
-
-        let mut stmt = db
-            .prepare("SELECT number, public_txs, private_txs FROM tx_privy WHERE number = ?")
+        let _ = db
+            .execute(
+                "INSERT INTO tx (hash, block_hash, block_number, private, latency_ms) VALUES (?1, ?2, ?3, ?4, ?5)",
+                params!["0x2", "0xb1", 1, true, Option::<u64>::None],
+            )
             .unwrap();
 
-        let mut privy_iter = stmt
-            .query_map([1], |row| {
-                let number: u64 = row.get(0)?;
-
-                let public_txs: String = row.get(1)?;
-                let public_txs: Vec<&str> = public_txs.split(",").collect();
-                let public_txs = public_txs.into_iter().map(|x| x.to_string()).collect();
-
-                let private_txs: String = row.get(2)?;
-                let private_txs: Vec<&str> = private_txs.split(",").collect();
-                let private_txs = private_txs.into_iter().map(|x| x.to_string()).collect();
+        // A block with no transactions at all must round-trip to empty
+        // vecs, not `[""]` (the bug the comma-joined TEXT column used to
+        // have).
+        let _ = db
+            .execute(
+                "INSERT INTO tx_privy (block_hash, number, canonical) VALUES (?1, ?2, ?3)",
+                params!["0xb2", 2, true],
+            )
+            .unwrap();
 
-                Ok(BlockPrivy {
-                    number,
-                    public_txs,
-                    private_txs,
-                })
-            })
+        // An orphaned block sitting at the same `number` as its canonical
+        // replacement must not shadow it: the replacement's row must still
+        // be the one `load_block_privy` resolves.
+        let _ = db
+            .execute(
+                "INSERT INTO tx_privy (block_hash, number, canonical) VALUES (?1, ?2, ?3)",
+                params!["0xb1-orphan", 1, false],
+            )
             .unwrap();
 
-        let result = privy_iter.next().unwrap().unwrap();
+        // `0x1` re-mined from the orphaned block into the canonical one at
+        // the same number must not collide on a shared primary key — the
+        // orphan's own copy of the tx (same hash, different block_hash)
+        // must coexist instead of getting silently dropped.
+        let _ = db
+            .execute(
+                "INSERT INTO tx (hash, block_hash, block_number, private, latency_ms) VALUES (?1, ?2, ?3, ?4, ?5)",
+                params!["0x1", "0xb1-orphan", 1, true, Option::<u64>::None],
+            )
+            .unwrap();
 
-        println!("privy_iter {:?}", result);
+        let result = load_block_privy(&db, 1).unwrap().unwrap();
+        assert_eq!(result.block_hash, "0xb1");
+        assert_eq!(result.public_txs, vec!["0x1".to_string()]);
+        assert_eq!(result.private_txs, vec!["0x2".to_string()]);
+
+        let empty = load_block_privy(&db, 2).unwrap().unwrap();
+        assert!(empty.public_txs.is_empty());
+        assert!(empty.private_txs.is_empty());
+
+        // The summary over the range must only count the canonical block's
+        // two txs, not the orphan's leftover row at the same number, but
+        // must still surface the orphan as a churn count.
+        let summary = load_privy_summary(&db, 1, 2).unwrap();
+        assert_eq!(summary.total_blocks, 2);
+        assert_eq!(summary.total_public, 1);
+        assert_eq!(summary.total_private, 1);
+        assert_eq!(summary.orphaned_blocks, 1);
+
+        // `include_orphaned = false` keeps today's canonical-only behavior.
+        let canonical_only = load_block_privy_range(&db, 1, 2, false).unwrap();
+        assert_eq!(canonical_only.len(), 2);
+        assert!(canonical_only.iter().all(|b| b.canonical));
+
+        // `include_orphaned = true` surfaces both rows at number 1 — the
+        // reorg churn a caller can't otherwise see.
+        let with_orphaned = load_block_privy_range(&db, 1, 2, true).unwrap();
+        assert_eq!(with_orphaned.len(), 3);
+        let at_one: Vec<&BlockPrivy> = with_orphaned.iter().filter(|b| b.number == 1).collect();
+        assert_eq!(at_one.len(), 2);
+        assert!(at_one.iter().any(|b| b.canonical && b.block_hash == "0xb1"));
+        assert!(at_one
+            .iter()
+            .any(|b| !b.canonical && b.block_hash == "0xb1-orphan"));
+
+        println!("privy {:?}", result);
     }
 }
@@ -0,0 +1,103 @@
+use std::{collections::VecDeque, net::SocketAddr};
+
+use metrics::{counter, describe_counter, describe_gauge, gauge};
+use metrics_exporter_prometheus::PrometheusBuilder;
+
+/// Width of the window `RollingRatio` averages over.
+const RATIO_WINDOW_BLOCKS: usize = 100;
+
+/// Tracks the private-tx ratio over the trailing `RATIO_WINDOW_BLOCKS`
+/// blocks instead of just the most recent one, so
+/// `reth_anal_private_ratio` reads as a trend operators can act on rather
+/// than a value that swings block-to-block.
+pub struct RollingRatio {
+    window: VecDeque<(usize, usize)>,
+    public_sum: usize,
+    private_sum: usize,
+}
+
+impl RollingRatio {
+    pub fn new() -> Self {
+        Self {
+            window: VecDeque::with_capacity(RATIO_WINDOW_BLOCKS),
+            public_sum: 0,
+            private_sum: 0,
+        }
+    }
+
+    /// Folds one block's counts into the window, evicting the oldest block
+    /// once it's over `RATIO_WINDOW_BLOCKS` wide, and returns the updated
+    /// ratio.
+    pub fn record(&mut self, public: usize, private: usize) -> f64 {
+        self.window.push_back((public, private));
+        self.public_sum += public;
+        self.private_sum += private;
+
+        if self.window.len() > RATIO_WINDOW_BLOCKS {
+            if let Some((old_public, old_private)) = self.window.pop_front() {
+                self.public_sum -= old_public;
+                self.private_sum -= old_private;
+            }
+        }
+
+        let total = self.public_sum + self.private_sum;
+        if total == 0 {
+            0.0
+        } else {
+            self.private_sum as f64 / total as f64
+        }
+    }
+}
+
+impl Default for RollingRatio {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Installs the Prometheus recorder and serves `/metrics` on `addr`. Call
+/// once, before the ingest task starts recording.
+pub fn install(addr: SocketAddr) {
+    PrometheusBuilder::new()
+        .with_http_listener(addr)
+        .install()
+        .expect("failed to install prometheus recorder");
+
+    describe_counter!(
+        "reth_anal_public_txs_total",
+        "Total public (mempool-seen) transactions classified"
+    );
+    describe_counter!(
+        "reth_anal_private_txs_total",
+        "Total private (never mempool-seen) transactions classified"
+    );
+    describe_gauge!(
+        "reth_anal_private_ratio",
+        "Rolling private-tx ratio over the trailing RATIO_WINDOW_BLOCKS blocks"
+    );
+    describe_gauge!(
+        "reth_anal_seen_txs_size",
+        "Current size of the in-memory mempool-seen set"
+    );
+    describe_counter!(
+        "reth_anal_queued_evictions_total",
+        "Transactions evicted from the queued pool during the every-10-blocks cleanup"
+    );
+}
+
+/// Records counters/gauges for one freshly classified block. `rolling_ratio`
+/// is the value returned by the caller's `RollingRatio::record` for this
+/// block, not recomputed here, since the window state lives with the
+/// ingest loop's other per-run state (alongside `seen_txs`).
+pub fn record_block(public: usize, private: usize, seen_txs_size: usize, rolling_ratio: f64) {
+    counter!("reth_anal_public_txs_total").increment(public as u64);
+    counter!("reth_anal_private_txs_total").increment(private as u64);
+
+    gauge!("reth_anal_private_ratio").set(rolling_ratio);
+    gauge!("reth_anal_seen_txs_size").set(seen_txs_size as f64);
+}
+
+/// Records queued-tx evictions from the every-10-blocks cleanup.
+pub fn record_queued_eviction(count: usize) {
+    counter!("reth_anal_queued_evictions_total").increment(count as u64);
+}